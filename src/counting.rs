@@ -0,0 +1,328 @@
+use crate::{double_hash_index, expand_hash, hash_item, BloomFilterError};
+use std::hash::Hash;
+
+/// A counting Bloom filter is a variant of [`crate::BloomFilter`] that replaces each bit with a
+/// small saturating counter, which makes it possible to [`remove`](CountingBloomFilter::remove)
+/// an element in addition to inserting and checking one.
+///
+/// Counters are stored as `u8` and use saturating arithmetic: once a counter reaches 255 it stops
+/// incrementing instead of wrapping around to zero. Increments beyond 255 are not remembered:
+/// `remove` still decrements a saturated counter, so 255 removals bring it back to zero however
+/// many insertions were made into it.
+///
+/// # Examples
+/// ```
+/// use bloomfilter_rs::CountingBloomFilter;
+///
+/// let mut cbf = CountingBloomFilter::new(1000, 3).unwrap();
+/// cbf.insert(&"test");
+/// assert!(cbf.check(&"test")); // true
+/// cbf.remove(&"test");
+/// assert!(!cbf.check(&"test")); // false
+/// ```
+pub struct CountingBloomFilter {
+    /// Per-bucket saturating counters, one per bit position a plain `BloomFilter` would use
+    counters: Vec<u8>,
+    /// Number of hash functions (bucket indices derived per item) to use
+    hash_count: usize,
+    /// Estimated number of items currently present, tracked via the minimum mapped counter for
+    /// each inserted/removed element so it stays accurate even once a counter saturates
+    item_count: u64,
+}
+
+impl CountingBloomFilter {
+    /// Creates a new counting Bloom filter with the specified size and number of hash functions
+    ///
+    /// # Arguments
+    /// * `size` - The number of counters (buckets) in the filter
+    /// * `hash_count` - The number of hash functions to use
+    ///
+    /// # Returns
+    /// A new counting Bloom filter or an error if the parameters are invalid
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::CountingBloomFilter;
+    ///
+    /// let cbf = CountingBloomFilter::new(1000, 3).unwrap();
+    /// ```
+    pub fn new(size: usize, hash_count: usize) -> Result<CountingBloomFilter, BloomFilterError> {
+        if size == 0 {
+            return Err(BloomFilterError::InvalidSize);
+        }
+        if hash_count == 0 {
+            return Err(BloomFilterError::InvalidHashCount);
+        }
+
+        Ok(CountingBloomFilter {
+            counters: vec![0u8; size],
+            hash_count,
+            item_count: 0,
+        })
+    }
+
+    /// Inserts an element into the counting Bloom filter
+    ///
+    /// Each of the `hash_count` mapped counters is incremented by one, saturating at `u8::MAX`
+    /// rather than wrapping around. `item_count` tracks the minimum of an element's mapped
+    /// counters (the standard counting-filter estimate of how many times it is present) rather
+    /// than a raw call count, so once a counter saturates, further inserts of the same element
+    /// stop inflating `item_count` — keeping it in sync with what `remove` can actually undo.
+    ///
+    /// # Arguments
+    /// * `word` - The element to insert
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::CountingBloomFilter;
+    ///
+    /// let mut cbf = CountingBloomFilter::new(1000, 3).unwrap();
+    /// cbf.insert(&"test");
+    /// ```
+    pub fn insert<T>(&mut self, word: &T)
+    where
+        T: Hash,
+    {
+        let (h1, h2) = expand_hash(hash_item(word));
+        let before = self.min_counter(h1, h2);
+
+        for i in 0..self.hash_count {
+            let idx = double_hash_index(h1, h2, i, self.counters.len());
+            self.counters[idx] = self.counters[idx].saturating_add(1);
+        }
+
+        let after = self.min_counter(h1, h2);
+        self.item_count += (after - before) as u64;
+    }
+
+    /// Removes an element from the counting Bloom filter
+    ///
+    /// Each of the `hash_count` mapped counters is decremented by one, saturating at `0` rather
+    /// than wrapping around. Like `insert`, `item_count` is adjusted by the change in the minimum
+    /// of the element's mapped counters rather than a flat one, so removing an element with at
+    /// least one mapped counter at zero (such as one that was never inserted and is not a false
+    /// positive) leaves `item_count` unchanged, and an element whose counters saturated during a
+    /// burst of inserts only gives back exactly what its counters can actually still account for.
+    /// Removing an element that was never inserted but whose mapped counters are all nonzero
+    /// decrements them and lowers `item_count`; this can leave shared counters too low for items
+    /// that collide with `word`, which may turn into false negatives for those other items. Only
+    /// remove items that are actually known to be present.
+    ///
+    /// # Arguments
+    /// * `word` - The element to remove
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::CountingBloomFilter;
+    ///
+    /// let mut cbf = CountingBloomFilter::new(1000, 3).unwrap();
+    /// cbf.insert(&"test");
+    /// cbf.remove(&"test");
+    /// assert!(!cbf.check(&"test"));
+    /// ```
+    pub fn remove<T>(&mut self, word: &T)
+    where
+        T: Hash,
+    {
+        let (h1, h2) = expand_hash(hash_item(word));
+        let before = self.min_counter(h1, h2);
+
+        for i in 0..self.hash_count {
+            let idx = double_hash_index(h1, h2, i, self.counters.len());
+            self.counters[idx] = self.counters[idx].saturating_sub(1);
+        }
+
+        let after = self.min_counter(h1, h2);
+        self.item_count -= (before - after) as u64;
+    }
+
+    /// Returns the smallest of an item's mapped counters, the standard counting-filter estimate
+    /// of how many times that item is currently present
+    ///
+    /// # Arguments
+    /// * `h1` - The first base hash value for the item
+    /// * `h2` - The second base hash value for the item
+    ///
+    /// # Returns
+    /// The minimum counter value across the item's `hash_count` mapped buckets
+    fn min_counter(&self, h1: u64, h2: u64) -> u8 {
+        (0..self.hash_count)
+            .map(|i| {
+                let idx = double_hash_index(h1, h2, i, self.counters.len());
+                self.counters[idx]
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Checks if an element is possibly in the set
+    ///
+    /// # Arguments
+    /// * `word` - The element to check
+    ///
+    /// # Returns
+    /// `true` if the element is possibly in the set, `false` if it is definitely not in the set
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::CountingBloomFilter;
+    ///
+    /// let mut cbf = CountingBloomFilter::new(1000, 3).unwrap();
+    /// cbf.insert(&"test");
+    /// assert!(cbf.check(&"test")); // true
+    /// ```
+    pub fn check<T>(&self, word: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (h1, h2) = expand_hash(hash_item(word));
+        for i in 0..self.hash_count {
+            let idx = double_hash_index(h1, h2, i, self.counters.len());
+            if self.counters[idx] == 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Clears all elements from the counting Bloom filter
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::CountingBloomFilter;
+    ///
+    /// let mut cbf = CountingBloomFilter::new(1000, 3).unwrap();
+    /// cbf.insert(&"test");
+    /// cbf.clear();
+    /// assert_eq!(cbf.len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.item_count = 0;
+    }
+
+    /// Returns the capacity of the counting Bloom filter (number of counters)
+    ///
+    /// # Returns
+    /// The number of counters in the filter
+    pub fn capacity(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// Returns the number of elements currently in the counting Bloom filter
+    ///
+    /// # Returns
+    /// The estimated number of items present: the sum of the changes in each inserted or removed
+    /// element's minimum mapped counter
+    pub fn len(&self) -> u64 {
+        self.item_count
+    }
+
+    /// Checks if the counting Bloom filter is empty
+    ///
+    /// # Returns
+    /// `true` if no elements are currently present, `false` otherwise
+    pub fn is_empty(&self) -> bool {
+        self.item_count == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_correct_size() {
+        let cbf = CountingBloomFilter::new(10, 1).unwrap();
+        assert!(cbf.counters.len() == 10);
+    }
+
+    #[test]
+    fn insert_and_check_str() {
+        let mut cbf = CountingBloomFilter::new(100, 4).unwrap();
+        cbf.insert(&"coffee");
+
+        assert!(cbf.check(&"coffee"));
+        assert!(!cbf.check(&"pancakes"));
+    }
+
+    #[test]
+    fn insert_remove_and_check() {
+        let mut cbf = CountingBloomFilter::new(100, 4).unwrap();
+        cbf.insert(&"coffee");
+        assert!(cbf.check(&"coffee"));
+
+        cbf.remove(&"coffee");
+        assert!(!cbf.check(&"coffee"));
+    }
+
+    #[test]
+    fn item_count_tracks_insert_and_remove() {
+        let mut cbf = CountingBloomFilter::new(100, 4).unwrap();
+        assert_eq!(cbf.len(), 0);
+
+        cbf.insert(&"coffee");
+        cbf.insert(&"ham");
+        assert_eq!(cbf.len(), 2);
+
+        cbf.remove(&"coffee");
+        assert_eq!(cbf.len(), 1);
+    }
+
+    #[test]
+    fn removing_an_absent_item_does_not_change_item_count() {
+        let mut cbf = CountingBloomFilter::new(100, 4).unwrap();
+        cbf.insert(&"coffee");
+        assert_eq!(cbf.len(), 1);
+
+        cbf.remove(&"never_inserted");
+
+        assert_eq!(cbf.len(), 1);
+        assert!(!cbf.is_empty());
+        assert!(cbf.check(&"coffee"));
+    }
+
+    #[test]
+    fn counters_saturate_instead_of_wrapping() {
+        let mut cbf = CountingBloomFilter::new(10, 1).unwrap();
+        for _ in 0..300 {
+            cbf.insert(&"hot");
+        }
+        assert!(cbf.check(&"hot"));
+
+        for _ in 0..300 {
+            cbf.remove(&"hot");
+        }
+        assert!(!cbf.check(&"hot"));
+    }
+
+    #[test]
+    fn item_count_stays_accurate_across_saturation() {
+        let mut cbf = CountingBloomFilter::new(10, 1).unwrap();
+        for _ in 0..300 {
+            cbf.insert(&"hot");
+        }
+        for _ in 0..300 {
+            cbf.remove(&"hot");
+        }
+
+        assert_eq!(cbf.len(), 0);
+        assert!(cbf.is_empty());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut cbf = CountingBloomFilter::new(100, 4).unwrap();
+        cbf.insert(&"test");
+        assert_eq!(cbf.len(), 1);
+        cbf.clear();
+        assert_eq!(cbf.len(), 0);
+        assert!(cbf.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(CountingBloomFilter::new(0, 1).is_err());
+        assert!(CountingBloomFilter::new(1, 0).is_err());
+    }
+}
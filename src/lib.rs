@@ -1,11 +1,13 @@
 use bitvec::prelude::*;
-use md5::{Md5, Digest};
-use sha2::Sha256;
+use md5::{Digest, Md5};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
+mod counting;
 mod memory_test;
 
+pub use counting::CountingBloomFilter;
+
 /// A Bloom filter is a space-efficient probabilistic data structure that is used to test whether an element is a member of a set.
 /// False positive matches are possible, but false negatives are not. In other words, a query returns either "possibly in set" or "definitely not in set".
 ///
@@ -21,23 +23,12 @@ mod memory_test;
 pub struct BloomFilter {
     /// Bit array representing the filter
     buckets: BitVec,
-    /// List of hash algorithms to use
-    hash_algorithms: Vec<HashAlgorithm>,
+    /// Number of hash functions (bucket indices derived per item) to use
+    hash_count: usize,
     /// Number of items inserted into the filter
     item_count: u64,
 }
 
-/// Hash algorithms supported by the Bloom filter
-#[derive(Clone, Copy)]
-enum HashAlgorithm {
-    /// Rust's default hasher
-    Default,
-    /// MD5 hash algorithm
-    MD5,
-    /// SHA256 hash algorithm
-    SHA256,
-}
-
 /// Errors that can occur when working with a Bloom filter
 #[derive(Debug)]
 pub enum BloomFilterError {
@@ -49,43 +40,81 @@ pub enum BloomFilterError {
     Overflow,
 }
 
-/// Computes a hash value for the given object using the specified algorithm
+/// Number of high bits of a raw hash that are never read when deriving bucket indices
+const RESERVED_HASH_BITS: u32 = 8;
+
+/// Mask that keeps only the bits of a raw hash consumed by [`BloomFilter::insert_hash`] and
+/// [`BloomFilter::check_hash`]. The remaining [`RESERVED_HASH_BITS`] high bits are never read, so
+/// callers can pack caller-defined metadata into the unused high bits of their stored hashes.
+pub const HASH_INDEX_MASK: u64 = u64::MAX >> RESERVED_HASH_BITS;
+
+/// Computes a single combined hash value for an item
+///
+/// Hashes the item once with Rust's default hasher, then once more into a 128-bit MD5 digest, and
+/// XORs the two 64-bit halves of that digest together. This combined value is what `insert`/
+/// `check` hand to [`BloomFilter::insert_hash`]/[`BloomFilter::check_hash`].
 ///
 /// # Arguments
 /// * `obj` - The object to hash
-/// * `algorithm` - The hash algorithm to use
 ///
 /// # Returns
 /// A 64-bit hash value
-fn hash_with_algorithm<T>(obj: &T, algorithm: HashAlgorithm) -> u64
+pub(crate) fn hash_item<T>(obj: &T) -> u64
 where
     T: Hash,
 {
-    match algorithm {
-        HashAlgorithm::Default => {
-            let mut hasher = DefaultHasher::new();
-            obj.hash(&mut hasher);
-            hasher.finish()
-        }
-        HashAlgorithm::MD5 => {
-            let mut temp_hasher = DefaultHasher::new();
-            obj.hash(&mut temp_hasher);
-            let hash_bytes = temp_hasher.finish().to_le_bytes();
-            let mut hasher = Md5::new();
-            hasher.update(&hash_bytes);
-            let result = hasher.finalize();
-            result.iter().fold(0u64, |acc, &x| (acc << 8) | x as u64)
-        }
-        HashAlgorithm::SHA256 => {
-            let mut temp_hasher = DefaultHasher::new();
-            obj.hash(&mut temp_hasher);
-            let hash_bytes = temp_hasher.finish().to_le_bytes();
-            let mut hasher = Sha256::new();
-            hasher.update(&hash_bytes);
-            let result = hasher.finalize();
-            result.iter().fold(0u64, |acc, &x| (acc << 8) | x as u64)
-        }
+    let mut seed_hasher = DefaultHasher::new();
+    obj.hash(&mut seed_hasher);
+    let seed = seed_hasher.finish().to_le_bytes();
+
+    let mut digest_hasher = Md5::new();
+    digest_hasher.update(&seed);
+    let digest = digest_hasher.finalize();
+
+    let lo = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+    let hi = u64::from_le_bytes(digest[8..16].try_into().unwrap());
+    lo ^ hi
+}
+
+/// Expands a single raw hash into the two base hash values used by the Kirsch-Mitzenmacher
+/// "less hashing, same performance" technique
+///
+/// Only the bits kept by [`HASH_INDEX_MASK`] are used as `h1`; every bucket index the item needs
+/// is then derived from `h1` and `h2` instead of re-hashing the item per hash function.
+///
+/// # Arguments
+/// * `hash` - The raw hash to expand
+///
+/// # Returns
+/// A pair `(h1, h2)` of base hash values, with `h2` forced odd (and so nonzero). For a bucket
+/// count that is a power of two this keeps successive indices distinct; for other bucket counts
+/// `h2` can still be a multiple of the bucket count, and then successive indices can coincide
+pub(crate) fn expand_hash(hash: u64) -> (u64, u64) {
+    let h1 = hash & HASH_INDEX_MASK;
+    let mut h2 = h1.rotate_left(32);
+    if h2 == 0 {
+        h2 = 1;
     }
+    h2 |= 1;
+
+    (h1, h2)
+}
+
+/// Derives the `i`-th bucket index from the two base hash values of an item
+///
+/// Implements `g_i(x) = (h1 + i * h2) mod m`.
+///
+/// # Arguments
+/// * `h1` - The first base hash value
+/// * `h2` - The second base hash value
+/// * `i` - The index of the hash function, in `0..hash_count`
+/// * `bucket_count` - The number of buckets to map into
+///
+/// # Returns
+/// A bucket index in `0..bucket_count`
+pub(crate) fn double_hash_index(h1: u64, h2: u64, i: usize, bucket_count: usize) -> usize {
+    let combined = h1.wrapping_add((i as u64).wrapping_mul(h2));
+    (combined as usize) % bucket_count
 }
 
 impl BloomFilter {
@@ -113,23 +142,54 @@ impl BloomFilter {
         }
 
         let buckets = bitvec![0; size];
-        let hash_algorithms = vec![
-            HashAlgorithm::Default,
-            HashAlgorithm::MD5,
-            HashAlgorithm::SHA256,
-        ]
-        .into_iter()
-        .cycle()
-        .take(hash_count)
-        .collect();
 
         Ok(BloomFilter {
             buckets,
-            hash_algorithms,
+            hash_count,
             item_count: 0,
         })
     }
 
+    /// Creates a new Bloom filter sized for a target false positive rate
+    ///
+    /// Computes the optimal bit-array size and number of hash functions for the expected number
+    /// of items, using the standard formulas `m = ceil(-(n * ln(p)) / (ln 2)^2)` and
+    /// `k = max(1, round((m / n) * ln 2))`.
+    ///
+    /// # Arguments
+    /// * `expected_items` - The number of items expected to be inserted
+    /// * `false_positive_rate` - The desired false positive rate, strictly between 0 and 1
+    ///
+    /// # Returns
+    /// A new Bloom filter sized for the given parameters, or an error if they are invalid
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// // Sized for 10,000 items at a 1% false positive rate
+    /// let bf = BloomFilter::with_error_rate(10_000, 0.01).unwrap();
+    /// ```
+    pub fn with_error_rate(
+        expected_items: usize,
+        false_positive_rate: f64,
+    ) -> Result<BloomFilter, BloomFilterError> {
+        if expected_items == 0 {
+            return Err(BloomFilterError::InvalidSize);
+        }
+        if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+            return Err(BloomFilterError::InvalidHashCount);
+        }
+
+        let n = expected_items as f64;
+        let ln2 = std::f64::consts::LN_2;
+
+        let size = (-(n * false_positive_rate.ln()) / (ln2 * ln2)).ceil() as usize;
+        let hash_count = (((size as f64 / n) * ln2).round() as usize).max(1);
+
+        Self::new(size, hash_count)
+    }
+
     /// Inserts an element into the Bloom filter
     ///
     /// # Arguments
@@ -146,11 +206,7 @@ impl BloomFilter {
     where
         T: Hash,
     {
-        for &algorithm in &self.hash_algorithms {
-            let i: usize = self.bloom_hash(word, algorithm);
-            self.buckets.set(i, true);
-        }
-        self.item_count += 1;
+        self.insert_hash(hash_item(word));
     }
 
     /// Checks if an element is possibly in the set
@@ -173,9 +229,61 @@ impl BloomFilter {
     where
         T: Hash,
     {
-        for &algorithm in &self.hash_algorithms {
-            let i: usize = self.bloom_hash(word, algorithm);
-            if !self.buckets[i] {
+        self.check_hash(hash_item(word))
+    }
+
+    /// Inserts a precomputed hash into the Bloom filter
+    ///
+    /// Equivalent to `insert`, but for callers that already have a hash value for their item (for
+    /// example a cached `precomputed-hash`) and want to skip re-hashing it. Only the bits kept by
+    /// [`HASH_INDEX_MASK`] are read, so any bits outside the mask can carry caller-defined
+    /// metadata without affecting which buckets are set.
+    ///
+    /// # Arguments
+    /// * `hash` - The precomputed hash of the element to insert
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let mut bf = BloomFilter::new(1000, 3).unwrap();
+    /// bf.insert_hash(0x1234_5678_9abc_def0);
+    /// assert!(bf.check_hash(0x1234_5678_9abc_def0));
+    /// ```
+    pub fn insert_hash(&mut self, hash: u64) {
+        let (h1, h2) = expand_hash(hash);
+        for i in 0..self.hash_count {
+            let idx = double_hash_index(h1, h2, i, self.buckets.len());
+            self.buckets.set(idx, true);
+        }
+        self.item_count += 1;
+    }
+
+    /// Checks if a precomputed hash is possibly in the set
+    ///
+    /// Equivalent to `check`, but for callers that already have a hash value for their item and
+    /// want to skip re-hashing it. Only the bits kept by [`HASH_INDEX_MASK`] are read, so any bits
+    /// outside the mask can carry caller-defined metadata without affecting the result.
+    ///
+    /// # Arguments
+    /// * `hash` - The precomputed hash of the element to check
+    ///
+    /// # Returns
+    /// `true` if the hash is possibly in the set, `false` if it is definitely not in the set
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let mut bf = BloomFilter::new(1000, 3).unwrap();
+    /// bf.insert_hash(0x1234_5678_9abc_def0);
+    /// assert!(bf.check_hash(0x1234_5678_9abc_def0));
+    /// ```
+    pub fn check_hash(&self, hash: u64) -> bool {
+        let (h1, h2) = expand_hash(hash);
+        for i in 0..self.hash_count {
+            let idx = double_hash_index(h1, h2, i, self.buckets.len());
+            if !self.buckets[idx] {
                 return false;
             }
         }
@@ -196,29 +304,13 @@ impl BloomFilter {
     /// println!("False positive probability: {}", error_rate);
     /// ```
     pub fn error_chance(&self) -> f32 {
-        let numerator = (self.hash_algorithms.len() as u64 * self.item_count) as f32;
+        let numerator = (self.hash_count as u64 * self.item_count) as f32;
         let denominator = self.buckets.len() as f32;
         let e_exponent = (-1.0 * numerator) / denominator;
-        let chance: f32 = (1.0 - e_exponent.exp()).powf(self.hash_algorithms.len() as f32);
+        let chance: f32 = (1.0 - e_exponent.exp()).powf(self.hash_count as f32);
         chance
     }
 
-    /// Computes a hash value for the given object and maps it to a bucket index
-    ///
-    /// # Arguments
-    /// * `word` - The object to hash
-    /// * `algorithm` - The hash algorithm to use
-    ///
-    /// # Returns
-    /// A bucket index
-    fn bloom_hash<T>(&self, word: &T, algorithm: HashAlgorithm) -> usize
-    where
-        T: Hash,
-    {
-        let the_hash: usize = hash_with_algorithm(word, algorithm) as usize;
-        the_hash % self.buckets.len()
-    }
-
     /// Clears all elements from the Bloom filter
     ///
     /// # Examples
@@ -284,6 +376,196 @@ impl BloomFilter {
     pub fn is_empty(&self) -> bool {
         self.item_count == 0
     }
+
+    /// Merges another filter into this one in place, keeping membership in either set
+    ///
+    /// Computes the bitwise OR of the two bit arrays, so the result reports "possibly present"
+    /// for anything either filter would have. This is exact (no extra false negatives) and is
+    /// useful for merging filters built independently, e.g. one per shard.
+    ///
+    /// # Arguments
+    /// * `other` - The filter to merge in
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if `other` has a different bucket count or hash count
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let mut a = BloomFilter::new(1000, 3).unwrap();
+    /// let mut b = BloomFilter::new(1000, 3).unwrap();
+    /// a.insert(&"apple");
+    /// b.insert(&"banana");
+    ///
+    /// a.union(&b).unwrap();
+    /// assert!(a.check(&"apple"));
+    /// assert!(a.check(&"banana"));
+    /// ```
+    pub fn union(&mut self, other: &BloomFilter) -> Result<(), BloomFilterError> {
+        self.check_compatible(other)?;
+
+        for i in 0..self.buckets.len() {
+            let merged = self.buckets[i] || other.buckets[i];
+            self.buckets.set(i, merged);
+        }
+        self.item_count += other.item_count;
+
+        Ok(())
+    }
+
+    /// Merges another filter into this one in place, keeping membership in both sets
+    ///
+    /// Computes the bitwise AND of the two bit arrays. The result is an over-approximation of the
+    /// common set: a bucket can end up set just because each filter set it for a different item,
+    /// so `len()` after an intersection is only an upper bound on the true shared count, not an
+    /// exact one.
+    ///
+    /// # Arguments
+    /// * `other` - The filter to intersect with
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if `other` has a different bucket count or hash count
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let mut a = BloomFilter::new(1000, 3).unwrap();
+    /// let mut b = BloomFilter::new(1000, 3).unwrap();
+    /// a.insert(&"apple");
+    /// a.insert(&"banana");
+    /// b.insert(&"banana");
+    ///
+    /// a.intersection(&b).unwrap();
+    /// assert!(a.check(&"banana"));
+    /// ```
+    pub fn intersection(&mut self, other: &BloomFilter) -> Result<(), BloomFilterError> {
+        self.check_compatible(other)?;
+
+        for i in 0..self.buckets.len() {
+            let merged = self.buckets[i] && other.buckets[i];
+            self.buckets.set(i, merged);
+        }
+        self.item_count = self.item_count.min(other.item_count);
+
+        Ok(())
+    }
+
+    /// Checks that another filter has the same bucket count and hash count as this one
+    ///
+    /// # Arguments
+    /// * `other` - The filter to compare against
+    ///
+    /// # Returns
+    /// `Ok(())` if the two filters can be combined, or an error describing the mismatch
+    fn check_compatible(&self, other: &BloomFilter) -> Result<(), BloomFilterError> {
+        if self.buckets.len() != other.buckets.len() {
+            return Err(BloomFilterError::InvalidSize);
+        }
+        if self.hash_count != other.hash_count {
+            return Err(BloomFilterError::InvalidHashCount);
+        }
+        Ok(())
+    }
+
+    /// Serializes the filter's bit array and metadata into a compact byte buffer
+    ///
+    /// The layout is the bucket count, hash count, and item count as three little-endian `u64`s,
+    /// followed by the bit array packed eight bits per byte. The result can be written to disk or
+    /// sent over the network and reconstructed later with [`BloomFilter::from_bytes`].
+    ///
+    /// # Returns
+    /// A byte buffer encoding this filter
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let mut bf = BloomFilter::new(1000, 3).unwrap();
+    /// bf.insert(&"test");
+    /// let bytes = bf.to_bytes();
+    /// let restored = BloomFilter::from_bytes(&bytes).unwrap();
+    /// assert!(restored.check(&"test"));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bucket_count = self.buckets.len() as u64;
+        let hash_count = self.hash_count as u64;
+
+        let mut bytes = Vec::with_capacity(24 + (self.buckets.len() + 7) / 8);
+        bytes.extend_from_slice(&bucket_count.to_le_bytes());
+        bytes.extend_from_slice(&hash_count.to_le_bytes());
+        bytes.extend_from_slice(&self.item_count.to_le_bytes());
+
+        for byte_bits in self.buckets.chunks(8) {
+            let mut byte = 0u8;
+            for (i, bit) in byte_bits.iter().enumerate() {
+                if *bit {
+                    byte |= 1 << i;
+                }
+            }
+            bytes.push(byte);
+        }
+
+        bytes
+    }
+
+    /// Reconstructs a Bloom filter from bytes produced by [`BloomFilter::to_bytes`]
+    ///
+    /// # Arguments
+    /// * `data` - The byte buffer to decode
+    ///
+    /// # Returns
+    /// The reconstructed filter, or an error if the header is malformed, the hash count is zero,
+    /// or the bit payload doesn't match the declared bucket count
+    ///
+    /// # Examples
+    /// ```
+    /// use bloomfilter_rs::BloomFilter;
+    ///
+    /// let bf = BloomFilter::new(1000, 3).unwrap();
+    /// let bytes = bf.to_bytes();
+    /// let restored = BloomFilter::from_bytes(&bytes).unwrap();
+    /// assert_eq!(restored.capacity(), bf.capacity());
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<BloomFilter, BloomFilterError> {
+        const HEADER_LEN: usize = 24;
+        if data.len() < HEADER_LEN {
+            return Err(BloomFilterError::InvalidSize);
+        }
+
+        let bucket_count = u64::from_le_bytes(data[0..8].try_into().unwrap()) as usize;
+        let hash_count = u64::from_le_bytes(data[8..16].try_into().unwrap()) as usize;
+        let item_count = u64::from_le_bytes(data[16..24].try_into().unwrap());
+
+        if bucket_count == 0 {
+            return Err(BloomFilterError::InvalidSize);
+        }
+        if hash_count == 0 {
+            return Err(BloomFilterError::InvalidHashCount);
+        }
+
+        let payload = &data[HEADER_LEN..];
+        // `div_ceil` divides before rounding up, so this can't overflow even for an
+        // attacker-controlled `bucket_count` as large as `usize::MAX`.
+        let expected_payload_len = bucket_count.div_ceil(8);
+        if payload.len() != expected_payload_len {
+            return Err(BloomFilterError::InvalidSize);
+        }
+
+        let mut buckets = bitvec![0; bucket_count];
+        for i in 0..bucket_count {
+            let byte = payload[i / 8];
+            let bit = (byte >> (i % 8)) & 1 == 1;
+            buckets.set(i, bit);
+        }
+
+        Ok(BloomFilter {
+            buckets,
+            hash_count,
+            item_count,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -361,4 +643,127 @@ mod tests {
         assert!(BloomFilter::new(0, 1).is_err());
         assert!(BloomFilter::new(1, 0).is_err());
     }
+
+    #[test]
+    fn with_error_rate_sizes_filter() {
+        let bf = BloomFilter::with_error_rate(10_000, 0.01).unwrap();
+        assert!(bf.capacity() > 10_000);
+        assert!(bf.hash_count >= 1);
+    }
+
+    #[test]
+    fn with_error_rate_rejects_invalid_params() {
+        assert!(BloomFilter::with_error_rate(0, 0.01).is_err());
+        assert!(BloomFilter::with_error_rate(1000, 0.0).is_err());
+        assert!(BloomFilter::with_error_rate(1000, 1.0).is_err());
+    }
+
+    #[test]
+    fn large_hash_count_still_separates_items() {
+        let mut bf = BloomFilter::new(10_000, 32).unwrap();
+        bf.insert(&"coffee");
+
+        assert!(bf.check(&"coffee"));
+        assert!(!bf.check(&"pancakes"));
+    }
+
+    #[test]
+    fn to_bytes_and_from_bytes_round_trip() {
+        let mut bf = BloomFilter::new(1000, 3).unwrap();
+        bf.insert(&"coffee");
+        bf.insert(&"ham");
+
+        let bytes = bf.to_bytes();
+        let restored = BloomFilter::from_bytes(&bytes).unwrap();
+
+        assert_eq!(restored.capacity(), bf.capacity());
+        assert_eq!(restored.len(), bf.len());
+        assert!(restored.check(&"coffee"));
+        assert!(restored.check(&"ham"));
+        assert!(!restored.check(&"pancakes"));
+    }
+
+    #[test]
+    fn insert_hash_and_check_hash_round_trip() {
+        let mut bf = BloomFilter::new(1000, 3).unwrap();
+        bf.insert_hash(42);
+
+        assert!(bf.check_hash(42));
+        assert!(!bf.check_hash(1337));
+    }
+
+    #[test]
+    fn insert_agrees_with_insert_hash() {
+        let mut bf = BloomFilter::new(1000, 3).unwrap();
+        bf.insert(&"coffee");
+
+        assert!(bf.check_hash(hash_item(&"coffee")));
+    }
+
+    #[test]
+    fn reserved_high_bits_do_not_affect_bucket_selection() {
+        let mut bf = BloomFilter::new(1000, 3).unwrap();
+        bf.insert_hash(0x00_34_5678_9abc_def0);
+
+        assert!(bf.check_hash(0xff_34_5678_9abc_def0));
+    }
+
+    #[test]
+    fn union_keeps_membership_in_either_filter() {
+        let mut a = BloomFilter::new(1000, 3).unwrap();
+        let mut b = BloomFilter::new(1000, 3).unwrap();
+        a.insert(&"apple");
+        b.insert(&"banana");
+
+        a.union(&b).unwrap();
+
+        assert!(a.check(&"apple"));
+        assert!(a.check(&"banana"));
+        assert_eq!(a.len(), 2);
+    }
+
+    #[test]
+    fn intersection_keeps_membership_in_both_filters() {
+        let mut a = BloomFilter::new(1000, 3).unwrap();
+        let mut b = BloomFilter::new(1000, 3).unwrap();
+        a.insert(&"apple");
+        a.insert(&"banana");
+        b.insert(&"banana");
+
+        a.intersection(&b).unwrap();
+
+        assert!(a.check(&"banana"));
+    }
+
+    #[test]
+    fn union_and_intersection_reject_mismatched_filters() {
+        let mut a = BloomFilter::new(1000, 3).unwrap();
+        let different_size = BloomFilter::new(500, 3).unwrap();
+        let different_hash_count = BloomFilter::new(1000, 4).unwrap();
+
+        assert!(a.union(&different_size).is_err());
+        assert!(a.union(&different_hash_count).is_err());
+        assert!(a.intersection(&different_size).is_err());
+        assert!(a.intersection(&different_hash_count).is_err());
+    }
+
+    #[test]
+    fn from_bytes_rejects_malformed_data() {
+        assert!(BloomFilter::from_bytes(&[0u8; 4]).is_err());
+
+        let bf = BloomFilter::new(1000, 3).unwrap();
+        let mut bytes = bf.to_bytes();
+        bytes.pop();
+        assert!(BloomFilter::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn from_bytes_rejects_huge_bucket_count_without_panicking() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+
+        assert!(BloomFilter::from_bytes(&bytes).is_err());
+    }
 }